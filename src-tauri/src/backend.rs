@@ -0,0 +1,560 @@
+use std::process::{Child, Command, Stdio};
+use std::time::{Duration, Instant};
+use std::thread;
+
+use crate::logging;
+
+/// How long we give the backend to exit on its own before SIGKILL-ing the
+/// whole process tree.
+const SHUTDOWN_GRACE_PERIOD: Duration = Duration::from_secs(5);
+
+/// Lifecycle state of the supervised backend, reported to the frontend via
+/// the `backend_status` command.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BackendStatus {
+    Starting,
+    Ready,
+    Crashed,
+    Stopped,
+}
+
+pub struct BackendProcess {
+    child: Option<Child>,
+    port: Option<u16>,
+    #[cfg(windows)]
+    job: Option<windows_job::JobHandle>,
+}
+
+impl BackendProcess {
+    pub fn new() -> Self {
+        Self {
+            child: None,
+            port: None,
+            #[cfg(windows)]
+            job: None,
+        }
+    }
+
+    pub fn start(&mut self, app_handle: tauri::AppHandle) -> Result<(), String> {
+        // Determine app data directory
+        let app_data_dir = resolve_app_data_dir(&app_handle)?;
+
+        // Ensure directory exists
+        std::fs::create_dir_all(&app_data_dir)
+            .map_err(|e| format!("Failed to create app data directory: {}", e))?;
+
+        // Determine backend port: an automatically chosen free port unless
+        // the user pinned one via MAX_PORT.
+        let port = crate::port::resolve_port()?;
+        self.port = Some(port);
+
+        // Determine backend path
+        let backend_path = if cfg!(debug_assertions) {
+            // In dev mode, resolve relative to project root
+            // Tauri might run from src-tauri directory, so we need to go up one level
+            let current_dir = std::env::current_dir()
+                .map_err(|e| format!("Failed to get current directory: {}", e))?;
+
+            // If we're in src-tauri, go up one level to project root
+            let project_root = if current_dir.file_name()
+                .and_then(|n| n.to_str())
+                .map(|n| n == "src-tauri")
+                .unwrap_or(false) {
+                current_dir.parent()
+                    .ok_or_else(|| "Failed to get project root directory".to_string())?
+                    .to_path_buf()
+            } else {
+                current_dir
+            };
+
+            project_root.join("backend")
+        } else {
+            // In production, try resource directory first, then fallback to executable directory
+            let resource_backend = app_handle
+                .path_resolver()
+                .resource_dir()
+                .map(|dir| dir.join("backend"));
+
+            // Fallback: look for backend next to the executable
+            let exe_dir = std::env::current_exe()
+                .ok()
+                .and_then(|exe| {
+                    // Get the parent directory without canonicalizing (which can cause issues on Windows)
+                    exe.parent().map(|p| p.to_path_buf())
+                })
+                .map(|dir| dir.join("backend"));
+
+            // Try resource directory first, then executable directory
+            if let Some(ref path) = resource_backend {
+                if path.exists() {
+                    path.clone()
+                } else if let Some(ref exe_path) = exe_dir {
+                    if exe_path.exists() {
+                        exe_path.clone()
+                    } else {
+                        return Err(format!(
+                            "Backend not found. Checked: {} and {}",
+                            path.display(),
+                            exe_path.display()
+                        ));
+                    }
+                } else {
+                    return Err("Failed to determine backend path".to_string());
+                }
+            } else if let Some(ref exe_path) = exe_dir {
+                if exe_path.exists() {
+                    exe_path.clone()
+                } else {
+                    return Err(format!("Backend not found at: {}", exe_path.display()));
+                }
+            } else {
+                return Err("Failed to determine backend path".to_string());
+            }
+        };
+
+        // Resolve which Node.js binary to launch: prefer the Node runtime we
+        // bundle as a Tauri sidecar so end users don't need anything
+        // pre-installed, and only fall back to a system install on PATH.
+        let node_path = resolve_node_binary(&app_handle)
+            .or_else(|| which::which(node_exe_name()).ok())
+            .ok_or_else(|| {
+                if cfg!(debug_assertions) {
+                    "Node.js not found. Expected a bundled runtime next to the app or Node.js on your system PATH (install from https://nodejs.org/).".to_string()
+                } else {
+                    "Node.js runtime not found. No bundled Node.js sidecar was found in the resource or executable directory, and no system Node.js install was found on PATH either.".to_string()
+                }
+            })?;
+
+        // Set environment variables for backend
+        let mut env_vars = std::collections::HashMap::<String, String>::new();
+        env_vars.insert("MAX_ENV".to_string(), "desktop".to_string());
+        env_vars.insert("MAX_PORT".to_string(), port.to_string());
+        env_vars.insert(
+            "MAX_APP_DATA_DIR".to_string(),
+            app_data_dir.to_string_lossy().to_string(),
+        );
+
+        // Build command to start backend
+        let server_js = backend_path.join("server.js");
+        if !server_js.exists() {
+            return Err(format!(
+                "Backend server.js not found at: {}\n\nBackend path: {}\n\nPlease ensure:\n1. The backend folder is bundled with the application\n2. The backend folder contains server.js\n3. Backend dependencies are installed (node_modules folder exists)",
+                server_js.display(),
+                backend_path.display()
+            ));
+        }
+
+        // Install backend dependencies if node_modules is missing or stale,
+        // instead of making the user run npm install by hand.
+        crate::install::ensure_dependencies_installed(&app_handle, &backend_path, &node_path)?;
+
+        // Log paths before moving server_js (convert to absolute paths for logging)
+        let backend_path_abs = backend_path.canonicalize()
+            .unwrap_or_else(|_| backend_path.clone());
+        let server_js_abs = server_js.canonicalize()
+            .unwrap_or_else(|_| server_js.clone());
+        tracing::info!("Starting backend from: {}", backend_path_abs.display());
+        tracing::info!("Node.js path: {}", node_path.display());
+        tracing::info!("Server.js path: {}", server_js_abs.display());
+        tracing::info!("App data dir: {}", app_data_dir.display());
+
+        // Ensure we use absolute paths for the command
+        // Convert to string and remove the \\?\ prefix if present (Node.js doesn't like it)
+        let backend_path_abs = backend_path.canonicalize()
+            .map_err(|e| format!("Failed to canonicalize backend path {}: {}", backend_path.display(), e))?;
+        let server_js_abs = server_js.canonicalize()
+            .map_err(|e| format!("Failed to canonicalize server.js path {}: {}", server_js.display(), e))?;
+
+        // Convert paths to strings and remove \\?\ prefix for Node.js compatibility
+        let backend_path_str = backend_path_abs.to_string_lossy().replace("\\\\?\\", "");
+        let server_js_str = server_js_abs.to_string_lossy().replace("\\\\?\\", "");
+
+        let log_dir = app_data_dir.join("logs");
+        std::fs::create_dir_all(&log_dir)
+            .map_err(|e| format!("Failed to create log directory at {}: {}", log_dir.display(), e))?;
+        let rotating_log = std::sync::Arc::new(
+            logging::RotatingLog::open(log_dir.join("backend.log"))
+                .map_err(|e| format!("Failed to open backend log file: {}", e))?,
+        );
+
+        let mut cmd = Command::new(&node_path);
+        // Use the path as a string (Node.js handles Windows paths correctly)
+        // Always pipe stdout/stderr (even in dev) so we can forward each
+        // line to the frontend as it's produced, in addition to the log file.
+        cmd.arg(&*server_js_str)
+            .current_dir(&*backend_path_str)
+            .envs(&env_vars)
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped());
+
+        // On Unix, put the backend in its own process group so we can signal
+        // the whole tree (Node's own children included) instead of just the
+        // immediate child.
+        #[cfg(unix)]
+        {
+            use std::os::unix::process::CommandExt;
+            cmd.process_group(0);
+        }
+
+        // On Windows, hide the console window in release builds
+        #[cfg(windows)]
+        if !cfg!(debug_assertions) {
+            use std::os::windows::process::CommandExt;
+            const CREATE_NO_WINDOW: u32 = 0x08000000;
+            cmd.creation_flags(CREATE_NO_WINDOW);
+        }
+
+        // Spawn backend process
+        tracing::info!("Spawning backend process...");
+
+        let mut child = cmd
+            .spawn()
+            .map_err(|e| format!("Failed to spawn backend process: {}. Node path: {}, Backend path: {}", e, node_path.display(), backend_path.display()))?;
+
+        // On Windows, a process group has no real teardown story, so we lean
+        // on a Job Object with KILL_ON_JOB_CLOSE: as long as the child (and
+        // anything it spawns) lives inside the job, closing the job handle
+        // is enough to take the whole tree down.
+        #[cfg(windows)]
+        {
+            self.job = windows_job::JobHandle::new().and_then(|job| {
+                if job.assign(&child) {
+                    Some(job)
+                } else {
+                    None
+                }
+            });
+            if self.job.is_none() {
+                tracing::warn!("Failed to place backend in a Job Object; falling back to taskkill /T on stop");
+            }
+        }
+
+        let stdout = child.stdout.take().expect("backend stdout was piped");
+        let stderr = child.stderr.take().expect("backend stderr was piped");
+        logging::stream_to_frontend(app_handle.clone(), rotating_log.clone(), "stdout", stdout);
+        logging::stream_to_frontend(app_handle.clone(), rotating_log.clone(), "stderr", stderr);
+
+        self.child = Some(child);
+        tracing::info!("Backend process spawned, waiting for health check...");
+
+        // Wait for backend to be ready
+        let backend_url = format!("http://127.0.0.1:{}/health", port);
+        let max_attempts = 30;
+        let mut attempts = 0;
+
+        while attempts < max_attempts {
+            match reqwest::blocking::get(&backend_url) {
+                Ok(response) => {
+                    if response.status().is_success() {
+                        tracing::info!("Backend is ready at {}", backend_url);
+                        return Ok(());
+                    }
+                }
+                Err(_) => {
+                    // Backend not ready yet
+                }
+            }
+
+            thread::sleep(Duration::from_millis(500));
+            attempts += 1;
+        }
+
+        // The health check never succeeded: kill the child we just spawned
+        // instead of leaving it orphaned. Otherwise the caller's next
+        // start() call overwrites self.child with a new process, and the
+        // failed one is never reachable by stop()/poll_exit() again.
+        self.stop();
+
+        Err(format!(
+            "Backend failed to start after {} attempts ({} seconds).\n\nPossible causes:\n1. Node.js not installed or not in PATH\n2. Backend dependencies not installed (run 'npm install' in backend folder)\n3. Port {} already in use\n4. Backend server.js has errors\n\nCheck the log file at: {}\\logs\\backend.log",
+            max_attempts,
+            max_attempts / 2,
+            port,
+            app_data_dir.display()
+        ))
+    }
+
+    /// Port the currently running backend is listening on, if started.
+    pub fn port(&self) -> Option<u16> {
+        self.port
+    }
+
+    /// Non-blocking check for whether the backend has exited on its own
+    /// (e.g. crashed). Used by the supervisor to detect crashes without
+    /// racing `stop()`'s own wait loop.
+    pub fn poll_exit(&mut self) -> Option<std::process::ExitStatus> {
+        let status = match self.child.as_mut()?.try_wait() {
+            Ok(Some(status)) => status,
+            _ => return None,
+        };
+
+        self.child = None;
+        #[cfg(windows)]
+        {
+            self.job = None;
+        }
+        Some(status)
+    }
+
+    /// Ask the backend to shut down, wait up to [`SHUTDOWN_GRACE_PERIOD`] for
+    /// the backend process itself to exit, then force-kill the whole tree.
+    /// Only the direct child is waited on: if it exits within the grace
+    /// period, the processes it spawned are not signalled again here.
+    pub fn stop(&mut self) {
+        let Some(mut child) = self.child.take() else { return };
+        let pid = child.id();
+
+        self.request_graceful_shutdown(pid);
+
+        let deadline = Instant::now() + SHUTDOWN_GRACE_PERIOD;
+        loop {
+            match child.try_wait() {
+                Ok(Some(_)) => return,
+                Ok(None) => {
+                    if Instant::now() >= deadline {
+                        break;
+                    }
+                    thread::sleep(Duration::from_millis(100));
+                }
+                Err(e) => {
+                    tracing::error!("Failed to poll backend process while stopping it: {}", e);
+                    break;
+                }
+            }
+        }
+
+        tracing::warn!(
+            "Backend did not exit within {:?} of requesting shutdown, force killing the process tree",
+            SHUTDOWN_GRACE_PERIOD
+        );
+        self.force_kill_tree(&mut child, pid);
+        let _ = child.wait();
+    }
+
+    /// Best-effort graceful shutdown request: hit the backend's `/shutdown`
+    /// endpoint, falling back to SIGTERM on the process group on Unix (there
+    /// is no equivalent cheap signal on Windows, so we just wait for the
+    /// grace period to elapse there before force-killing the job).
+    fn request_graceful_shutdown(&self, pid: u32) {
+        if let Some(port) = self.port {
+            let url = format!("http://127.0.0.1:{}/shutdown", port);
+            if reqwest::blocking::Client::new()
+                .post(&url)
+                .timeout(Duration::from_secs(1))
+                .send()
+                .is_ok()
+            {
+                return;
+            }
+        }
+
+        #[cfg(unix)]
+        unsafe {
+            libc::kill(-(pid as libc::pid_t), libc::SIGTERM);
+        }
+        #[cfg(not(unix))]
+        let _ = pid;
+    }
+
+    #[cfg(unix)]
+    fn force_kill_tree(&mut self, child: &mut Child, pid: u32) {
+        unsafe {
+            libc::kill(-(pid as libc::pid_t), libc::SIGKILL);
+        }
+        let _ = child.kill();
+    }
+
+    #[cfg(windows)]
+    fn force_kill_tree(&mut self, child: &mut Child, pid: u32) {
+        // Dropping the job handle (if we have one) tears down every process
+        // it contains thanks to JOB_OBJECT_LIMIT_KILL_ON_JOB_CLOSE.
+        if self.job.take().is_some() {
+            let _ = child.kill();
+            return;
+        }
+
+        // No job object (e.g. creation failed on spawn): fall back to
+        // `taskkill /T` to reap the tree as best we can.
+        let _ = Command::new("taskkill")
+            .args(["/PID", &pid.to_string(), "/T", "/F"])
+            .output();
+        let _ = child.kill();
+    }
+}
+
+impl Drop for BackendProcess {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+/// App data directory the backend stores its data and logs under: a path
+/// relative to the project root in dev, or the platform app data dir in
+/// production. Shared by `start()` and anything that needs to locate the
+/// backend log without starting the backend (e.g. the `read_backend_log`
+/// command).
+pub fn resolve_app_data_dir(app_handle: &tauri::AppHandle) -> Result<std::path::PathBuf, String> {
+    if cfg!(debug_assertions) {
+        let current_dir = std::env::current_dir()
+            .map_err(|e| format!("Failed to get current directory: {}", e))?;
+
+        // If we're in src-tauri, go up one level to project root
+        let project_root = if current_dir.file_name()
+            .and_then(|n| n.to_str())
+            .map(|n| n == "src-tauri")
+            .unwrap_or(false) {
+            current_dir.parent()
+                .ok_or_else(|| "Failed to get project root directory".to_string())?
+                .to_path_buf()
+        } else {
+            current_dir
+        };
+
+        Ok(project_root.join("backend").join("storage"))
+    } else {
+        let config = app_handle.config();
+        Ok(tauri::api::path::app_data_dir(&*config)
+            .ok_or_else(|| "Failed to get app data directory".to_string())?
+            .join("data"))
+    }
+}
+
+/// Where `BackendProcess::start` writes the backend's rotating log file.
+pub fn backend_log_path(app_handle: &tauri::AppHandle) -> Result<std::path::PathBuf, String> {
+    Ok(resolve_app_data_dir(app_handle)?.join("logs").join("backend.log"))
+}
+
+fn node_exe_name() -> &'static str {
+    if cfg!(target_os = "windows") {
+        "node.exe"
+    } else {
+        "node"
+    }
+}
+
+/// Rust target triple for the platform we're running on, matching the
+/// suffix convention Tauri expects for bundled sidecar/external binaries
+/// (e.g. `node-x86_64-pc-windows-msvc.exe`).
+fn target_triple() -> &'static str {
+    if cfg!(all(target_os = "windows", target_arch = "x86_64")) {
+        "x86_64-pc-windows-msvc"
+    } else if cfg!(all(target_os = "windows", target_arch = "aarch64")) {
+        "aarch64-pc-windows-msvc"
+    } else if cfg!(all(target_os = "macos", target_arch = "x86_64")) {
+        "x86_64-apple-darwin"
+    } else if cfg!(all(target_os = "macos", target_arch = "aarch64")) {
+        "aarch64-apple-darwin"
+    } else if cfg!(all(target_os = "linux", target_arch = "x86_64")) {
+        "x86_64-unknown-linux-gnu"
+    } else if cfg!(all(target_os = "linux", target_arch = "aarch64")) {
+        "aarch64-unknown-linux-gnu"
+    } else {
+        "unknown"
+    }
+}
+
+/// Looks for a Node.js runtime we bundled ourselves, as a Tauri
+/// sidecar/external binary named `node-<target-triple>[.exe]`. Checked in
+/// the resource directory first (where `tauri.conf.json`'s
+/// `bundle.externalBin` entries land), then next to our own executable, so
+/// both installed and portable builds find it.
+///
+/// TODO(bundle the sidecar): this is only the lookup half of "ship our own
+/// Node so end users need nothing pre-installed" — it resolves a sidecar if
+/// one was bundled, but nothing bundles one yet. This source tree has no
+/// `tauri.conf.json`, no icons, and no frontend build output at all (it's a
+/// `src-tauri/src` snapshot), so the `bundle.externalBin` entry and the
+/// `node-<target-triple>[.exe]` binaries it would point at don't belong in
+/// this change — they depend on project scaffolding (and a source of actual
+/// Node binaries to vendor) that lives outside what's here. Tracking this
+/// explicitly as unfinished rather than calling the feature done: until the
+/// scaffolding + externalBin wiring lands, this always falls through to the
+/// system `node` on PATH, same as before this lookup existed.
+fn resolve_node_binary(app_handle: &tauri::AppHandle) -> Option<std::path::PathBuf> {
+    let exe_suffix = if cfg!(target_os = "windows") { ".exe" } else { "" };
+    let sidecar_name = format!("node-{}{}", target_triple(), exe_suffix);
+
+    if let Some(resource_dir) = app_handle.path_resolver().resource_dir() {
+        let candidate = resource_dir.join(&sidecar_name);
+        if candidate.exists() {
+            return Some(candidate);
+        }
+    }
+
+    if let Ok(exe_dir) = std::env::current_exe().map(|exe| exe.parent().map(|p| p.to_path_buf())) {
+        if let Some(exe_dir) = exe_dir {
+            let candidate = exe_dir.join(&sidecar_name);
+            if candidate.exists() {
+                return Some(candidate);
+            }
+        }
+    }
+
+    tracing::debug!(
+        "No bundled Node sidecar found (expected '{}' in the resource or executable directory); falling back to PATH. \
+         This requires a bundle.externalBin entry in tauri.conf.json that this source tree does not have.",
+        sidecar_name
+    );
+    None
+}
+
+#[cfg(windows)]
+mod windows_job {
+    use std::os::windows::io::AsRawHandle;
+    use std::ptr::null_mut;
+    use winapi::um::handleapi::CloseHandle;
+    use winapi::um::jobapi2::{AssignProcessToJobObject, CreateJobObjectW, SetInformationJobObject};
+    use winapi::um::winnt::{
+        JobObjectExtendedLimitInformation, HANDLE, JOBOBJECT_EXTENDED_LIMIT_INFORMATION,
+        JOB_OBJECT_LIMIT_KILL_ON_JOB_CLOSE,
+    };
+
+    /// Wraps a Windows Job Object configured to kill every process it
+    /// contains as soon as the handle is closed, so stopping the backend
+    /// reaps the whole tree even if Node spawned grandchildren of its own.
+    pub struct JobHandle(HANDLE);
+
+    impl JobHandle {
+        pub fn new() -> Option<Self> {
+            unsafe {
+                let handle = CreateJobObjectW(null_mut(), null_mut());
+                if handle.is_null() {
+                    return None;
+                }
+
+                let mut info: JOBOBJECT_EXTENDED_LIMIT_INFORMATION = std::mem::zeroed();
+                info.BasicLimitInformation.LimitFlags = JOB_OBJECT_LIMIT_KILL_ON_JOB_CLOSE;
+
+                let ok = SetInformationJobObject(
+                    handle,
+                    JobObjectExtendedLimitInformation,
+                    &mut info as *mut _ as *mut _,
+                    std::mem::size_of::<JOBOBJECT_EXTENDED_LIMIT_INFORMATION>() as u32,
+                );
+                if ok == 0 {
+                    CloseHandle(handle);
+                    return None;
+                }
+
+                Some(Self(handle))
+            }
+        }
+
+        pub fn assign(&self, child: &std::process::Child) -> bool {
+            unsafe { AssignProcessToJobObject(self.0, child.as_raw_handle() as HANDLE) != 0 }
+        }
+    }
+
+    impl Drop for JobHandle {
+        fn drop(&mut self) {
+            unsafe {
+                CloseHandle(self.0);
+            }
+        }
+    }
+
+    // Safety: the job handle is only ever touched from the thread that owns
+    // `BackendProcess`'s mutex, same as the `Child` it is paired with.
+    unsafe impl Send for JobHandle {}
+}
@@ -0,0 +1,55 @@
+use std::sync::{Arc, Mutex};
+
+use serde::Serialize;
+
+use crate::backend::{self, BackendProcess, BackendStatus};
+use crate::logging;
+use crate::supervisor::Supervisor;
+
+/// Response shape for the `backend_status` command: the frontend gets both
+/// the lifecycle state and the port in one round trip instead of having to
+/// piece it together from events.
+#[derive(Serialize)]
+pub struct BackendStatusResponse {
+    status: BackendStatus,
+    port: Option<u16>,
+}
+
+#[tauri::command]
+pub fn backend_status(
+    backend: tauri::State<'_, Arc<Mutex<BackendProcess>>>,
+    supervisor: tauri::State<'_, Supervisor>,
+) -> BackendStatusResponse {
+    BackendStatusResponse {
+        status: supervisor.status(),
+        port: backend.lock().unwrap().port(),
+    }
+}
+
+#[tauri::command]
+pub fn backend_port(backend: tauri::State<'_, Arc<Mutex<BackendProcess>>>) -> Option<u16> {
+    backend.lock().unwrap().port()
+}
+
+#[tauri::command]
+pub fn restart_backend(supervisor: tauri::State<'_, Supervisor>) {
+    supervisor.request_restart();
+}
+
+/// Pauses the backend. Asynchronous: the run loop notices the pause flag
+/// and stops the process on its own next poll (well under a second), rather
+/// than this command blocking on it directly — callers that need to know
+/// the stop has actually completed should poll `backend_status`. The loop
+/// stays alive so a later `restart_backend` can bring the backend back
+/// without restarting the whole app; `request_shutdown` is reserved for app
+/// exit, where there's no run loop left to resume.
+#[tauri::command]
+pub fn stop_backend(supervisor: tauri::State<'_, Supervisor>) {
+    supervisor.request_pause();
+}
+
+#[tauri::command]
+pub fn read_backend_log(app_handle: tauri::AppHandle, tail_lines: usize) -> Result<Vec<String>, String> {
+    let log_path = backend::backend_log_path(&app_handle)?;
+    logging::read_log_tail(&log_path, tail_lines)
+}
@@ -0,0 +1,317 @@
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+use sha2::{Digest, Sha256};
+use tauri::Manager;
+
+/// Name of the advisory lock file guarding concurrent installs. Several app
+/// windows/instances can point at the same `backend` directory, so this
+/// needs a lock that works across processes, not just threads.
+const LOCK_FILE_NAME: &str = ".install.lock";
+/// Records the `package-lock.json` hash we last installed from, so repeat
+/// launches can skip reinstalling when nothing changed.
+const HASH_MARKER_FILE_NAME: &str = ".install-hash";
+
+/// Makes sure `backend_path/node_modules` exists and matches the current
+/// `package-lock.json`, running `npm ci`/`npm install` if not. If no npm can
+/// be found, an existing `node_modules` is used as-is even when stale. Safe
+/// to call from multiple app instances concurrently: an advisory file lock
+/// over the backend directory serializes the install, and the fast path
+/// (lock file already up to date) is checked only after acquiring the lock.
+///
+/// `node_path` is the Node.js binary we resolved to launch the backend with
+/// (bundled sidecar or system install, see `backend::resolve_node_binary`);
+/// npm is located relative to it so a machine with no system Node/npm can
+/// still install dependencies off the bundled runtime.
+pub fn ensure_dependencies_installed(
+    app_handle: &tauri::AppHandle,
+    backend_path: &Path,
+    node_path: &Path,
+) -> Result<(), String> {
+    let lock_path = backend_path.join(LOCK_FILE_NAME);
+    let lock_file = std::fs::OpenOptions::new()
+        .create(true)
+        .write(true)
+        .open(&lock_path)
+        .map_err(|e| format!("Failed to open backend install lock at {}: {}", lock_path.display(), e))?;
+
+    let mut file_lock = fd_lock::RwLock::new(lock_file);
+    let _guard = file_lock
+        .write()
+        .map_err(|e| format!("Failed to acquire backend install lock at {}: {}", lock_path.display(), e))?;
+
+    let node_modules = backend_path.join("node_modules");
+    let hash_marker = backend_path.join(HASH_MARKER_FILE_NAME);
+    let current_hash = package_lock_hash(backend_path);
+    let recorded_hash = std::fs::read_to_string(&hash_marker).ok();
+
+    let up_to_date = node_modules.exists()
+        && match &current_hash {
+            // We have a package-lock.json: only trust the install if its
+            // hash matches what we recorded after the last install.
+            Some(hash) => recorded_hash.as_deref() == Some(hash.as_str()),
+            // No package-lock.json to compare against; an existing
+            // node_modules is the best signal we have.
+            None => true,
+        };
+
+    if up_to_date {
+        return Ok(());
+    }
+
+    let Some(npm) = locate_npm(node_path) else {
+        // A Tauri `externalBin` sidecar is a single renamed binary with
+        // nothing bundled alongside it, so on an end-user machine with no
+        // system Node/npm either, there's no npm to run at all. If
+        // node_modules is already there (vendored into the bundle at build
+        // time rather than installed at runtime), use it as-is instead of
+        // failing on a package-lock.json change we have no way to act on.
+        if node_modules.exists() {
+            tracing::warn!(
+                "No npm available (checked next to the resolved Node runtime at {} and on \
+                 PATH) to refresh node_modules in {}; continuing with what's already there.",
+                node_path.display(),
+                backend_path.display()
+            );
+            return Ok(());
+        }
+        return Err(format!(
+            "Cannot install backend dependencies: no npm found next to the Node runtime at {} \
+             and none on PATH, and {} has no node_modules to fall back on. A single-binary \
+             Node sidecar doesn't carry npm with it - either bundle node_modules into the app \
+             ahead of time, or bundle a full Node distribution (with npm) instead of a bare \
+             externalBin binary.",
+            node_path.display(),
+            backend_path.display()
+        ));
+    };
+
+    let _ = app_handle.emit_all("backend-installing", ());
+
+    let use_ci = backend_path.join("package-lock.json").exists();
+    let install_arg = if use_ci { "ci" } else { "install" };
+
+    let mut child = npm
+        .command(install_arg)
+        .current_dir(backend_path)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("Failed to run 'npm {}' in {}: {}", install_arg, backend_path.display(), e))?;
+
+    let stdout = child.stdout.take().expect("npm child stdout was piped");
+    let stderr = child.stderr.take().expect("npm child stderr was piped");
+
+    let stdout_handle = {
+        let app_handle = app_handle.clone();
+        std::thread::spawn(move || stream_install_output(stdout, &app_handle))
+    };
+    let stderr_handle = {
+        let app_handle = app_handle.clone();
+        std::thread::spawn(move || stream_install_output(stderr, &app_handle))
+    };
+    let _ = stdout_handle.join();
+    let _ = stderr_handle.join();
+
+    let status = child
+        .wait()
+        .map_err(|e| format!("Failed to wait for 'npm {}': {}", install_arg, e))?;
+
+    if !status.success() {
+        return Err(format!(
+            "'npm {}' failed with {} while installing backend dependencies in {}",
+            install_arg,
+            status,
+            backend_path.display()
+        ));
+    }
+
+    if let Some(hash) = current_hash {
+        let _ = std::fs::write(&hash_marker, hash);
+    }
+
+    Ok(())
+}
+
+/// Where we found an npm we can actually run. Unlike the old "just shell
+/// out to npm on PATH" approach, `locate_npm` returns `None` rather than a
+/// command that's liable to fail with "program not found" when nothing
+/// usable exists - see the call site for why that distinction matters for
+/// an `externalBin` sidecar, which has no npm bundled alongside it.
+enum NpmLocation {
+    /// An `npm`/`npm.cmd` binary next to `node_path` (a full Node
+    /// distribution bundled as the sidecar ships one alongside `node`).
+    NextToNode(PathBuf),
+    /// npm's own JS entry point, run through our Node binary directly.
+    CliJs { node_path: PathBuf, npm_cli_js: PathBuf },
+    /// `npm`/`npm.cmd` on PATH, for a system Node install.
+    OnPath,
+}
+
+impl NpmLocation {
+    fn command(&self, install_arg: &str) -> Command {
+        let mut cmd = match self {
+            NpmLocation::NextToNode(npm_bin) => Command::new(npm_bin),
+            NpmLocation::CliJs { node_path, npm_cli_js } => {
+                let mut cmd = Command::new(node_path);
+                cmd.arg(npm_cli_js);
+                cmd
+            }
+            NpmLocation::OnPath => Command::new(npm_exe_name()),
+        };
+        cmd.arg(install_arg);
+        cmd
+    }
+}
+
+fn npm_exe_name() -> &'static str {
+    if cfg!(target_os = "windows") { "npm.cmd" } else { "npm" }
+}
+
+/// Looks for an npm we can reach off the resolved Node runtime before
+/// falling back to a system install on PATH. Returns `None` if nothing
+/// usable was found anywhere, rather than a command doomed to fail with
+/// "program not found" - an `externalBin` sidecar is a bare renamed binary
+/// with no npm bundled next to it, so that's the expected outcome on an
+/// end-user machine with no system Node/npm either.
+fn locate_npm(node_path: &Path) -> Option<NpmLocation> {
+    if let Some(dir) = node_path.parent() {
+        let npm_bin = dir.join(npm_exe_name());
+        if npm_bin.exists() {
+            return Some(NpmLocation::NextToNode(npm_bin));
+        }
+
+        let npm_cli_js = npm_cli_js_path(dir);
+        if npm_cli_js.exists() {
+            return Some(NpmLocation::CliJs { node_path: node_path.to_path_buf(), npm_cli_js });
+        }
+    }
+
+    if which::which(npm_exe_name()).is_ok() {
+        return Some(NpmLocation::OnPath);
+    }
+
+    None
+}
+
+fn npm_cli_js_path(node_dir: &Path) -> PathBuf {
+    node_dir
+        .join("node_modules")
+        .join("npm")
+        .join("bin")
+        .join("npm-cli.js")
+}
+
+fn stream_install_output(reader: impl std::io::Read, app_handle: &tauri::AppHandle) {
+    for line in BufReader::new(reader).lines().map_while(Result::ok) {
+        tracing::info!(target: "backend-install", "{}", line);
+        let _ = app_handle.emit_all("backend-install-log", &line);
+    }
+}
+
+fn package_lock_hash(backend_path: &Path) -> Option<String> {
+    let contents = std::fs::read(backend_path.join("package-lock.json")).ok()?;
+    let mut hasher = Sha256::new();
+    hasher.update(&contents);
+    Some(format!("{:x}", hasher.finalize()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    /// A scratch directory under the OS temp dir, unique per test so
+    /// parallel test runs don't collide, cleaned up on drop.
+    struct ScratchDir(PathBuf);
+
+    impl ScratchDir {
+        fn new(name: &str) -> Self {
+            let dir = std::env::temp_dir().join(format!(
+                "max-install-test-{}-{}-{}",
+                name,
+                std::process::id(),
+                std::time::SystemTime::now()
+                    .duration_since(std::time::UNIX_EPOCH)
+                    .unwrap()
+                    .as_nanos()
+            ));
+            fs::create_dir_all(&dir).unwrap();
+            Self(dir)
+        }
+    }
+
+    impl Drop for ScratchDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    #[test]
+    fn locate_npm_prefers_npm_binary_next_to_node() {
+        let scratch = ScratchDir::new("prefers-binary");
+        let node_path = scratch.0.join(if cfg!(windows) { "node.exe" } else { "node" });
+        fs::write(&node_path, "").unwrap();
+        fs::write(scratch.0.join(npm_exe_name()), "").unwrap();
+        let npm_cli_js = npm_cli_js_path(&scratch.0);
+        fs::create_dir_all(npm_cli_js.parent().unwrap()).unwrap();
+        fs::write(&npm_cli_js, "").unwrap();
+
+        match locate_npm(&node_path) {
+            Some(NpmLocation::NextToNode(path)) => assert_eq!(path, scratch.0.join(npm_exe_name())),
+            other => panic!("expected NextToNode, got {}", describe(&other)),
+        }
+    }
+
+    #[test]
+    fn locate_npm_falls_back_to_cli_js_when_no_npm_binary() {
+        let scratch = ScratchDir::new("falls-back-to-cli-js");
+        let node_path = scratch.0.join(if cfg!(windows) { "node.exe" } else { "node" });
+        fs::write(&node_path, "").unwrap();
+        let npm_cli_js = npm_cli_js_path(&scratch.0);
+        fs::create_dir_all(npm_cli_js.parent().unwrap()).unwrap();
+        fs::write(&npm_cli_js, "").unwrap();
+
+        match locate_npm(&node_path) {
+            Some(NpmLocation::CliJs { npm_cli_js: path, .. }) => assert_eq!(path, npm_cli_js),
+            other => panic!("expected CliJs, got {}", describe(&other)),
+        }
+    }
+
+    #[test]
+    fn locate_npm_finds_nothing_next_to_a_bare_sidecar() {
+        // An externalBin sidecar is just the renamed node binary, with
+        // nothing bundled alongside it - locate_npm must not invent a
+        // command that's doomed to fail with "program not found". Point
+        // PATH at the (npm-less) scratch dir itself so the assertion holds
+        // regardless of whether the machine running this test happens to
+        // have npm installed.
+        let scratch = ScratchDir::new("bare-sidecar");
+        let node_path = scratch.0.join(if cfg!(windows) { "node.exe" } else { "node" });
+        fs::write(&node_path, "").unwrap();
+
+        let original_path = std::env::var_os("PATH");
+        unsafe {
+            std::env::set_var("PATH", &scratch.0);
+        }
+        let result = locate_npm(&node_path);
+        unsafe {
+            match &original_path {
+                Some(path) => std::env::set_var("PATH", path),
+                None => std::env::remove_var("PATH"),
+            }
+        }
+
+        assert!(result.is_none());
+    }
+
+    fn describe(location: &Option<NpmLocation>) -> &'static str {
+        match location {
+            Some(NpmLocation::NextToNode(_)) => "NextToNode",
+            Some(NpmLocation::CliJs { .. }) => "CliJs",
+            Some(NpmLocation::OnPath) => "OnPath",
+            None => "None",
+        }
+    }
+}
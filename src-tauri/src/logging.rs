@@ -0,0 +1,216 @@
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufRead, BufReader, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::Serialize;
+use tauri::Manager;
+use tracing_subscriber::EnvFilter;
+
+/// Threshold before `backend.log` rolls over to `backend.log.1`.
+const ROTATE_AT_BYTES: u64 = 5 * 1024 * 1024;
+/// How many rotated files to keep around (`backend.log.1` .. `.N`).
+const MAX_ROTATED_FILES: u32 = 5;
+
+/// Installs the global `tracing` subscriber for the app. Honors `RUST_LOG`
+/// (e.g. `RUST_LOG=debug`) and defaults to `info` otherwise.
+pub fn init() {
+    let filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info"));
+    tracing_subscriber::fmt()
+        .with_env_filter(filter)
+        .with_target(false)
+        .init();
+}
+
+/// A line of backend output forwarded to the frontend as a `backend-log`
+/// event, alongside a best-effort parsed level and a capture timestamp.
+#[derive(Clone, Serialize)]
+struct BackendLogLine {
+    stream: &'static str,
+    level: &'static str,
+    line: String,
+    timestamp_ms: u128,
+}
+
+/// Spawns a thread that reads `reader` line by line, forwarding each line to
+/// the frontend as a `backend-log` event and appending it to the rotating
+/// backend log file. `stream` is `"stdout"` or `"stderr"`, used only to tag
+/// the emitted events.
+pub fn stream_to_frontend(
+    app_handle: tauri::AppHandle,
+    log: Arc<RotatingLog>,
+    stream: &'static str,
+    reader: impl Read + Send + 'static,
+) {
+    std::thread::spawn(move || {
+        for line in BufReader::new(reader).lines().map_while(Result::ok) {
+            let level = guess_level(&line);
+            match level {
+                "error" => tracing::error!(target: "backend", "{}", line),
+                "warn" => tracing::warn!(target: "backend", "{}", line),
+                "debug" => tracing::debug!(target: "backend", "{}", line),
+                _ => tracing::info!(target: "backend", "{}", line),
+            }
+
+            log.write_line(&line);
+
+            let payload = BackendLogLine {
+                stream,
+                level,
+                line,
+                timestamp_ms: SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .map(|d| d.as_millis())
+                    .unwrap_or(0),
+            };
+            let _ = app_handle.emit_all("backend-log", payload);
+        }
+    });
+}
+
+/// Best-effort level sniffing: the backend's own log lines aren't ours to
+/// format, so we look for common level markers to give the frontend
+/// something to color/filter by.
+fn guess_level(line: &str) -> &'static str {
+    let lower = line.to_ascii_lowercase();
+    if lower.contains("error") || lower.contains("panic") {
+        "error"
+    } else if lower.contains("warn") {
+        "warn"
+    } else if lower.contains("debug") {
+        "debug"
+    } else {
+        "info"
+    }
+}
+
+/// Appends lines to `backend.log`, rotating to `backend.log.1..N` once the
+/// active file crosses [`ROTATE_AT_BYTES`], so long-running sessions don't
+/// fill the disk.
+pub struct RotatingLog {
+    path: PathBuf,
+    writer: Mutex<BufWriter<File>>,
+}
+
+impl RotatingLog {
+    pub fn open(path: PathBuf) -> std::io::Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
+        Ok(Self {
+            path,
+            writer: Mutex::new(BufWriter::new(file)),
+        })
+    }
+
+    pub fn write_line(&self, line: &str) {
+        let mut writer = self.writer.lock().unwrap();
+        if let Err(e) = writeln!(writer, "{}", line) {
+            tracing::error!("Failed to write to backend log: {}", e);
+            return;
+        }
+        let _ = writer.flush();
+        drop(writer);
+        self.rotate_if_needed();
+    }
+
+    fn rotate_if_needed(&self) {
+        if !exceeds(&self.path, ROTATE_AT_BYTES) {
+            return;
+        }
+
+        // Re-check after acquiring the lock in case another thread beat us
+        // to the rotation.
+        let mut writer = self.writer.lock().unwrap();
+        if !exceeds(&self.path, ROTATE_AT_BYTES) {
+            return;
+        }
+
+        for n in (1..MAX_ROTATED_FILES).rev() {
+            let from = rotated_path(&self.path, n);
+            let to = rotated_path(&self.path, n + 1);
+            if from.exists() {
+                let _ = fs::rename(&from, &to);
+            }
+        }
+        let _ = fs::rename(&self.path, rotated_path(&self.path, 1));
+
+        match OpenOptions::new().create(true).append(true).open(&self.path) {
+            Ok(file) => *writer = BufWriter::new(file),
+            Err(e) => tracing::error!("Failed to reopen backend log after rotation: {}", e),
+        }
+    }
+}
+
+fn exceeds(path: &Path, limit: u64) -> bool {
+    fs::metadata(path).map(|meta| meta.len() >= limit).unwrap_or(false)
+}
+
+fn rotated_path(base: &Path, n: u32) -> PathBuf {
+    let mut name = base.as_os_str().to_owned();
+    name.push(format!(".{}", n));
+    PathBuf::from(name)
+}
+
+/// Reads the last `tail_lines` lines of the backend log file, used by the
+/// `read_backend_log` command so a troubleshooting UI can show recent
+/// output without the frontend having to keep its own buffer.
+pub fn read_log_tail(path: &Path, tail_lines: usize) -> Result<Vec<String>, String> {
+    let contents = fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read log file at {}: {}", path.display(), e))?;
+    let lines: Vec<&str> = contents.lines().collect();
+    let start = lines.len().saturating_sub(tail_lines);
+    Ok(lines[start..].iter().map(|s| s.to_string()).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct ScratchFile(PathBuf);
+
+    impl ScratchFile {
+        fn new(name: &str) -> Self {
+            let path = std::env::temp_dir().join(format!(
+                "max-logging-test-{}-{}-{}",
+                name,
+                std::process::id(),
+                SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_nanos()
+            ));
+            Self(path)
+        }
+    }
+
+    impl Drop for ScratchFile {
+        fn drop(&mut self) {
+            let _ = fs::remove_file(&self.0);
+        }
+    }
+
+    #[test]
+    fn exceeds_is_false_under_the_limit() {
+        let file = ScratchFile::new("under-limit");
+        fs::write(&file.0, "short").unwrap();
+        assert!(!exceeds(&file.0, 1024));
+    }
+
+    #[test]
+    fn exceeds_is_true_at_or_over_the_limit() {
+        let file = ScratchFile::new("over-limit");
+        fs::write(&file.0, vec![b'x'; 10]).unwrap();
+        assert!(exceeds(&file.0, 10));
+        assert!(exceeds(&file.0, 5));
+    }
+
+    #[test]
+    fn exceeds_is_false_for_a_missing_file() {
+        let file = ScratchFile::new("missing");
+        assert!(!exceeds(&file.0, 0));
+    }
+
+    #[test]
+    fn rotated_path_appends_the_generation_suffix() {
+        let base = Path::new("/var/data/backend.log");
+        assert_eq!(rotated_path(base, 1), Path::new("/var/data/backend.log.1"));
+        assert_eq!(rotated_path(base, 5), Path::new("/var/data/backend.log.5"));
+    }
+}
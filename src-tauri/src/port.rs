@@ -0,0 +1,56 @@
+use std::net::TcpListener;
+
+/// Env var that, when set to `1`, allows falling back to an OS-assigned
+/// port if the explicit `MAX_PORT` the user configured is already taken.
+const ALLOW_FALLBACK_ENV: &str = "MAX_ALLOW_PORT_FALLBACK";
+
+/// Picks the port the backend should listen on. If `MAX_PORT` is unset, asks
+/// the OS for a free ephemeral port so launches never collide on the old
+/// hardcoded default of 4000. If `MAX_PORT` is set but already occupied, we
+/// error by default with the port and the bind error (the process holding
+/// the port is not identified); set `MAX_ALLOW_PORT_FALLBACK=1` to instead
+/// fall back to an OS-assigned free port.
+pub fn resolve_port() -> Result<u16, String> {
+    match std::env::var("MAX_PORT") {
+        Ok(value) => {
+            let requested: u16 = value
+                .parse()
+                .map_err(|_| format!("Invalid MAX_PORT value: {:?}", value))?;
+
+            match TcpListener::bind(("127.0.0.1", requested)) {
+                Ok(listener) => {
+                    drop(listener);
+                    Ok(requested)
+                }
+                Err(e) => {
+                    if std::env::var(ALLOW_FALLBACK_ENV).as_deref() == Ok("1") {
+                        tracing::warn!(
+                            "MAX_PORT={} is already in use ({}); falling back to a free port since {} is set",
+                            requested, e, ALLOW_FALLBACK_ENV
+                        );
+                        allocate_free_port()
+                    } else {
+                        Err(format!(
+                            "Port {} already in use ({}). Set {}=1 to fall back to an automatically chosen port instead of failing.",
+                            requested, e, ALLOW_FALLBACK_ENV
+                        ))
+                    }
+                }
+            }
+        }
+        Err(_) => allocate_free_port(),
+    }
+}
+
+fn allocate_free_port() -> Result<u16, String> {
+    let listener = TcpListener::bind(("127.0.0.1", 0))
+        .map_err(|e| format!("Failed to allocate a free port: {}", e))?;
+    let port = listener
+        .local_addr()
+        .map_err(|e| format!("Failed to read allocated port: {}", e))?
+        .port();
+    // Drop the listener before handing the port to the backend so it's free
+    // for the backend to bind by the time it starts listening.
+    drop(listener);
+    Ok(port)
+}
@@ -0,0 +1,366 @@
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+use tauri::Manager;
+
+use crate::backend::{BackendProcess, BackendStatus};
+
+/// Payload for the `backend-ready` event, telling the frontend which port
+/// to connect to (it may have been auto-assigned rather than `MAX_PORT`).
+#[derive(Serialize)]
+struct BackendReadyPayload {
+    port: Option<u16>,
+}
+
+/// Restart policy for the backend supervisor, overridable via env vars so
+/// desktop builds can tune it without a code change (mirrors `MAX_PORT`).
+pub struct SupervisorConfig {
+    /// Give up restarting once more than this many crashes happen inside
+    /// `crash_window`.
+    pub max_attempts: u32,
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+    pub crash_window: Duration,
+}
+
+impl SupervisorConfig {
+    pub fn from_env() -> Self {
+        Self {
+            max_attempts: env_u32("MAX_BACKEND_MAX_RESTARTS", 5),
+            initial_backoff: Duration::from_millis(env_u64("MAX_BACKEND_BACKOFF_MS", 500)),
+            max_backoff: Duration::from_millis(env_u64("MAX_BACKEND_MAX_BACKOFF_MS", 30_000)),
+            crash_window: Duration::from_millis(env_u64("MAX_BACKEND_CRASH_WINDOW_MS", 60_000)),
+        }
+    }
+}
+
+fn env_u32(key: &str, default: u32) -> u32 {
+    std::env::var(key).ok().and_then(|v| v.parse().ok()).unwrap_or(default)
+}
+
+fn env_u64(key: &str, default: u64) -> u64 {
+    std::env::var(key).ok().and_then(|v| v.parse().ok()).unwrap_or(default)
+}
+
+/// Outcome of waiting for the backend to stop, so the main loop can tell a
+/// crash apart from a restart or a user-requested stop.
+enum WaitOutcome {
+    Crashed(std::process::ExitStatus),
+    RestartRequested,
+    Paused,
+}
+
+/// Owns the thread that keeps the backend alive: starts it, watches for it
+/// exiting unexpectedly, and restarts it with exponential backoff. Also
+/// tracks the backend's current lifecycle status for the `backend_status`
+/// command.
+///
+/// Distinguishes a permanent app-exit `shutdown` (which ends the run loop's
+/// thread entirely) from a user-requested `pause` (`stop_backend`, which
+/// leaves the loop alive but idle so a later `restart_backend` can bring the
+/// backend back without restarting the whole app).
+pub struct Supervisor {
+    shutdown: Arc<AtomicBool>,
+    paused: Arc<AtomicBool>,
+    restart: Arc<AtomicBool>,
+    status: Arc<Mutex<BackendStatus>>,
+}
+
+impl Supervisor {
+    pub fn spawn(
+        app_handle: tauri::AppHandle,
+        backend: Arc<Mutex<BackendProcess>>,
+        config: SupervisorConfig,
+    ) -> Self {
+        let shutdown = Arc::new(AtomicBool::new(false));
+        let paused = Arc::new(AtomicBool::new(false));
+        let restart = Arc::new(AtomicBool::new(false));
+        let status = Arc::new(Mutex::new(BackendStatus::Starting));
+
+        let shutdown_clone = shutdown.clone();
+        let paused_clone = paused.clone();
+        let restart_clone = restart.clone();
+        let status_clone = status.clone();
+        thread::spawn(move || {
+            Self::run(app_handle, backend, config, shutdown_clone, paused_clone, restart_clone, status_clone)
+        });
+
+        Self { shutdown, paused, restart, status }
+    }
+
+    /// Current lifecycle status of the supervised backend.
+    pub fn status(&self) -> BackendStatus {
+        *self.status.lock().unwrap()
+    }
+
+    /// Tell the supervisor loop to stop restarting the backend and end its
+    /// thread. Must be called before `BackendProcess::stop()` during app
+    /// exit, or the supervisor will see the resulting exit as a crash and
+    /// resurrect it. This is permanent for the life of the app — use
+    /// `request_pause` for a stop the user can recover from with
+    /// `request_restart`.
+    pub fn request_shutdown(&self) {
+        self.shutdown.store(true, Ordering::SeqCst);
+        *self.status.lock().unwrap() = BackendStatus::Stopped;
+    }
+
+    /// Used by `stop_backend`: stops the backend but keeps the run loop
+    /// alive and idle, so `request_restart` can bring it back later. Unlike
+    /// `request_shutdown`, this does not end the supervisor's thread. The
+    /// status turns `Stopped` once the run loop has actually stopped it.
+    pub fn request_pause(&self) {
+        self.paused.store(true, Ordering::SeqCst);
+    }
+
+    /// Ask the supervisor to (re)start the backend. If it's currently
+    /// running, this cycles it (doesn't count against the restart
+    /// backoff/crash-window budget); if it was paused via `request_pause`,
+    /// this resumes the run loop. Both flags are set regardless of which
+    /// case applies, since `request_pause` runs in a different thread and
+    /// may not have been observed by the run loop yet — leaving `restart`
+    /// set covers a pause that's still in flight, and the run loop clears
+    /// it once it actually resumes from a paused wait.
+    pub fn request_restart(&self) {
+        self.paused.store(false, Ordering::SeqCst);
+        self.restart.store(true, Ordering::SeqCst);
+    }
+
+    fn run(
+        app_handle: tauri::AppHandle,
+        backend: Arc<Mutex<BackendProcess>>,
+        config: SupervisorConfig,
+        shutdown: Arc<AtomicBool>,
+        paused: Arc<AtomicBool>,
+        restart: Arc<AtomicBool>,
+        status: Arc<Mutex<BackendStatus>>,
+    ) {
+        let mut backoff = config.initial_backoff;
+        let mut recent_crashes: VecDeque<Instant> = VecDeque::new();
+
+        loop {
+            if shutdown.load(Ordering::SeqCst) {
+                return;
+            }
+
+            if paused.load(Ordering::SeqCst) {
+                *status.lock().unwrap() = BackendStatus::Stopped;
+                if !Self::wait_while_paused(&paused, &shutdown) {
+                    return;
+                }
+                // We're resuming from a pause, not honoring a separate
+                // restart request `request_restart` may have also set to
+                // cover the race of calling it before this pause was
+                // observed; consume it so we don't cycle the freshly
+                // started backend right back down again.
+                restart.store(false, Ordering::SeqCst);
+            }
+
+            *status.lock().unwrap() = BackendStatus::Starting;
+            let start_result = {
+                let mut guard = backend.lock().unwrap();
+                guard.start(app_handle.clone())
+            };
+
+            match start_result {
+                Ok(()) => {
+                    backoff = config.initial_backoff;
+                    let port = backend.lock().unwrap().port();
+                    tracing::info!("Backend started successfully on port {:?}", port);
+                    *status.lock().unwrap() = BackendStatus::Ready;
+                    let _ = app_handle.emit_all("backend-ready", BackendReadyPayload { port });
+                }
+                Err(e) => {
+                    tracing::error!("Failed to start backend: {}", e);
+                    *status.lock().unwrap() = BackendStatus::Crashed;
+                    let _ = app_handle.emit_all("backend-error", e.clone());
+
+                    // Belt and suspenders alongside start()'s own cleanup:
+                    // make sure nothing from the failed attempt is left
+                    // running before we retry on the same BackendProcess,
+                    // or the next start() would overwrite self.child and
+                    // orphan it for good.
+                    backend.lock().unwrap().stop();
+
+                    if Self::note_crash(&mut recent_crashes, &config) {
+                        tracing::error!(
+                            "Backend failed to start {} times within {:?}, giving up",
+                            recent_crashes.len(),
+                            config.crash_window
+                        );
+                        let _ = app_handle.emit_all("backend-crash-loop", ());
+                        return;
+                    }
+
+                    if !Self::sleep_unless_shutdown(backoff, &shutdown) {
+                        return;
+                    }
+                    backoff = (backoff * 2).min(config.max_backoff);
+                    continue;
+                }
+            }
+
+            // Wait for a shutdown request, a manual restart request, or the
+            // backend exiting on its own (a crash, since a requested stop
+            // sets `shutdown` first).
+            let outcome = loop {
+                if shutdown.load(Ordering::SeqCst) {
+                    return;
+                }
+                if paused.load(Ordering::SeqCst) {
+                    tracing::info!("Backend stop requested");
+                    backend.lock().unwrap().stop();
+                    *status.lock().unwrap() = BackendStatus::Stopped;
+                    break WaitOutcome::Paused;
+                }
+                if restart.swap(false, Ordering::SeqCst) {
+                    tracing::info!("Backend restart requested");
+                    backend.lock().unwrap().stop();
+                    break WaitOutcome::RestartRequested;
+                }
+                match backend.lock().unwrap().poll_exit() {
+                    Some(exit_status) => break WaitOutcome::Crashed(exit_status),
+                    None => thread::sleep(Duration::from_millis(250)),
+                }
+            };
+
+            if shutdown.load(Ordering::SeqCst) {
+                return;
+            }
+
+            match outcome {
+                WaitOutcome::RestartRequested => {
+                    let _ = app_handle.emit_all("backend-restarting", 0u64);
+                    backoff = config.initial_backoff;
+                    continue;
+                }
+                WaitOutcome::Paused => {
+                    if !Self::wait_while_paused(&paused, &shutdown) {
+                        return;
+                    }
+                    // See the comment at the top-of-loop pause check: clear
+                    // a `restart` that was only set to cover this same race.
+                    restart.store(false, Ordering::SeqCst);
+                    backoff = config.initial_backoff;
+                    continue;
+                }
+                WaitOutcome::Crashed(exit_status) => {
+                    tracing::warn!("Backend exited unexpectedly: {:?}", exit_status);
+                    *status.lock().unwrap() = BackendStatus::Crashed;
+                    let _ = app_handle.emit_all("backend-crashed", exit_status.code());
+                }
+            }
+
+            if Self::note_crash(&mut recent_crashes, &config) {
+                tracing::error!(
+                    "Backend crashed {} times within {:?}, giving up",
+                    recent_crashes.len(),
+                    config.crash_window
+                );
+                let _ = app_handle.emit_all("backend-crash-loop", ());
+                return;
+            }
+
+            let _ = app_handle.emit_all("backend-restarting", backoff.as_millis() as u64);
+            if !Self::sleep_unless_shutdown(backoff, &shutdown) {
+                return;
+            }
+            backoff = (backoff * 2).min(config.max_backoff);
+        }
+    }
+
+    /// Records a crash (whether it's a failed `start()` or the backend
+    /// exiting unexpectedly afterwards) and prunes anything outside
+    /// `config.crash_window`. Returns `true` once that leaves more than
+    /// `config.max_attempts` crashes in the window, i.e. time to give up
+    /// instead of restarting again — a permanently-broken `start()` counts
+    /// against the same budget as a runtime crash loop.
+    fn note_crash(recent_crashes: &mut VecDeque<Instant>, config: &SupervisorConfig) -> bool {
+        while let Some(&oldest) = recent_crashes.front() {
+            if oldest.elapsed() > config.crash_window {
+                recent_crashes.pop_front();
+            } else {
+                break;
+            }
+        }
+        recent_crashes.push_back(Instant::now());
+        recent_crashes.len() as u32 > config.max_attempts
+    }
+
+    /// Blocks until `request_restart` clears `paused` (or `request_shutdown`
+    /// ends the app) so `stop_backend` leaves the backend idle rather than
+    /// ending the run loop's thread. Returns `false` if shutdown was
+    /// requested while waiting.
+    fn wait_while_paused(paused: &AtomicBool, shutdown: &AtomicBool) -> bool {
+        while paused.load(Ordering::SeqCst) {
+            if shutdown.load(Ordering::SeqCst) {
+                return false;
+            }
+            thread::sleep(Duration::from_millis(100));
+        }
+        !shutdown.load(Ordering::SeqCst)
+    }
+
+    /// Sleeps in short increments so a shutdown request during the backoff
+    /// window is noticed promptly. Returns `false` if shutdown was requested.
+    fn sleep_unless_shutdown(duration: Duration, shutdown: &AtomicBool) -> bool {
+        let deadline = Instant::now() + duration;
+        while Instant::now() < deadline {
+            if shutdown.load(Ordering::SeqCst) {
+                return false;
+            }
+            thread::sleep(Duration::from_millis(100));
+        }
+        !shutdown.load(Ordering::SeqCst)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(max_attempts: u32, crash_window: Duration) -> SupervisorConfig {
+        SupervisorConfig {
+            max_attempts,
+            initial_backoff: Duration::from_millis(1),
+            max_backoff: Duration::from_millis(1),
+            crash_window,
+        }
+    }
+
+    #[test]
+    fn note_crash_does_not_give_up_under_the_budget() {
+        let config = config(3, Duration::from_secs(60));
+        let mut recent_crashes = VecDeque::new();
+
+        assert!(!Supervisor::note_crash(&mut recent_crashes, &config));
+        assert!(!Supervisor::note_crash(&mut recent_crashes, &config));
+        assert!(!Supervisor::note_crash(&mut recent_crashes, &config));
+    }
+
+    #[test]
+    fn note_crash_gives_up_once_over_the_budget() {
+        let config = config(2, Duration::from_secs(60));
+        let mut recent_crashes = VecDeque::new();
+
+        assert!(!Supervisor::note_crash(&mut recent_crashes, &config));
+        assert!(!Supervisor::note_crash(&mut recent_crashes, &config));
+        assert!(Supervisor::note_crash(&mut recent_crashes, &config));
+    }
+
+    #[test]
+    fn note_crash_prunes_crashes_outside_the_window() {
+        let config = config(1, Duration::from_millis(0));
+        let mut recent_crashes = VecDeque::new();
+
+        // Every crash is already "outside" a zero-length window by the time
+        // the next one is recorded, so the budget never trips.
+        assert!(!Supervisor::note_crash(&mut recent_crashes, &config));
+        assert!(!Supervisor::note_crash(&mut recent_crashes, &config));
+        assert!(!Supervisor::note_crash(&mut recent_crashes, &config));
+        assert_eq!(recent_crashes.len(), 1);
+    }
+}